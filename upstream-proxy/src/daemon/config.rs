@@ -6,10 +6,19 @@
 //! Crate configuration.
 
 use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::str::FromStr;
+use std::time::Duration;
 
 use futures::stream::BoxStream;
 
-use librad::{net, net::discovery, paths, PeerId, Signer};
+use librad::{
+    git::{Oid, Urn},
+    net,
+    net::discovery,
+    paths,
+    PeerId,
+    Signer,
+};
 
 lazy_static::lazy_static! {
     /// Localhost binding to any available port, i.e. `127.0.0.1:0`.
@@ -17,29 +26,156 @@ lazy_static::lazy_static! {
         SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 0));
 }
 
+/// Tunables for [`configure`] that govern which Radicle network a peer joins
+/// and how it behaves within it.
+///
+/// Defaults match what [`configure`] used to hardcode, so running isolated
+/// test/private networks or tuning fanout/replication for larger fleets only
+/// requires overriding the fields that matter.
+#[derive(Clone, Debug, Default)]
+pub struct ProtocolParams {
+    /// Network identifier. Peers on different networks never interconnect.
+    pub network: net::Network,
+    /// Gossip membership fanout/active-view sizes.
+    pub membership: net::protocol::membership::Params,
+    /// Replication concurrency and limits.
+    pub replication: net::replication::Config,
+}
+
 /// Configure a [`net::peer::Config`].
+///
+/// `request_pull` governs which peers, if any, may ask us to fetch refs on
+/// demand; pass [`net::protocol::config::DenyAll`] to keep the previous
+/// behaviour of refusing every request-pull.
+///
+/// `advertised_addrs` are the externally reachable address(es) announced to
+/// the membership protocol, distinct from `listen_addr`. Pass `None` when
+/// `listen_addr` is already reachable as-is, e.g. on a bare public IP; pass
+/// `Some` addresses when bound to a private interface or behind NAT.
 #[must_use]
-pub fn configure<S>(paths: paths::Paths, signer: S, listen_addr: SocketAddr) -> net::peer::Config<S>
+pub fn configure<S, P>(
+    paths: paths::Paths,
+    signer: S,
+    listen_addr: SocketAddr,
+    advertised_addrs: Option<Vec<SocketAddr>>,
+    protocol_params: ProtocolParams,
+    request_pull: P,
+) -> net::peer::Config<S, P>
 where
     S: Signer + Clone + Send + Sync + 'static,
     S::Error: std::error::Error + Send + Sync + 'static,
 {
+    let ProtocolParams {
+        network,
+        membership,
+        replication,
+    } = protocol_params;
+
     net::peer::Config {
         signer,
         protocol: net::protocol::Config {
             paths,
             listen_addr,
-            advertised_addrs: None,
-            membership: net::protocol::membership::Params::default(),
-            network: net::Network::default(),
-            replication: net::replication::Config::default(),
+            advertised_addrs,
+            membership,
+            network,
+            replication,
             rate_limits: net::protocol::Quota::default(),
-            request_pull: net::protocol::config::DenyAll,
+            request_pull,
         },
         storage: net::peer::config::Storage::default(),
     }
 }
 
+/// Configure a [`net::peer::Config`] together with a bootstrap [`discovery::Discovery`].
+///
+/// This is a thin wrapper around [`configure`] that lets callers pick a concrete
+/// discovery mechanism, e.g. [`NoDiscovery`] or [`DnsDiscovery`], without having to
+/// juggle the peer config and the discovery stream separately.
+#[must_use]
+pub fn configure_with_discovery<S, P, D>(
+    paths: paths::Paths,
+    signer: S,
+    listen_addr: SocketAddr,
+    advertised_addrs: Option<Vec<SocketAddr>>,
+    protocol_params: ProtocolParams,
+    request_pull: P,
+    discovery: D,
+) -> (net::peer::Config<S, P>, D::Stream)
+where
+    S: Signer + Clone + Send + Sync + 'static,
+    S::Error: std::error::Error + Send + Sync + 'static,
+    D: discovery::Discovery<Addr = SocketAddr>,
+{
+    let config = configure(
+        paths,
+        signer,
+        listen_addr,
+        advertised_addrs,
+        protocol_params,
+        request_pull,
+    );
+    (config, discovery.discover())
+}
+
+// NOT IMPLEMENTED: network diagnostic event stream for RPC tracing
+// (membership joins/neighbours, gossip have/want, request-pull traffic).
+//
+// An earlier pass landed `configure_with_diagnostics`/`Diagnostics`/
+// `NetworkDiagnosticEvent` here, but `Diagnostics::emit` was never called by
+// anything: this module only builds `net::peer::Config`, it has no access to
+// a running peer's protocol event loop to translate into diagnostic events,
+// so the API was a disconnected stub. It was removed rather than kept as
+// dead weight. Delivering this for real requires wiring into wherever the
+// peer's protocol events are actually produced once it is running, which
+// lives outside this module/crate snapshot. Tracked as not-yet-implemented;
+// do not re-land an `emit()` that nothing calls.
+
+/// An allow-list of [`PeerId`]s permitted to request pulls for any [`Urn`].
+///
+/// Everyone not on the list is denied, mirroring the strictness of
+/// [`net::protocol::config::DenyAll`] but carving out trusted mirrors/relays
+/// that are allowed to ask us to fetch refs on demand.
+#[derive(Clone, Debug, Default)]
+pub struct AllowedPeers(std::collections::HashSet<PeerId>);
+
+impl AllowedPeers {
+    /// Permit exactly `peers` to request pulls.
+    pub fn new(peers: impl IntoIterator<Item = PeerId>) -> Self {
+        Self(peers.into_iter().collect())
+    }
+}
+
+impl net::protocol::config::RequestPullGuard for AllowedPeers {
+    fn allow(&self, peer: &PeerId, _urn: &Urn) -> bool {
+        self.0.contains(peer)
+    }
+}
+
+/// A request-pull policy backed by an arbitrary predicate over the
+/// requesting peer and the [`Urn`] it is asking us to pull.
+#[derive(Clone)]
+pub struct RequestPullPredicate<F>(F);
+
+impl<F> RequestPullPredicate<F>
+where
+    F: Fn(&PeerId, &Urn) -> bool + Clone,
+{
+    /// Permit a request-pull exactly when `predicate` returns `true`.
+    pub fn new(predicate: F) -> Self {
+        Self(predicate)
+    }
+}
+
+impl<F> net::protocol::config::RequestPullGuard for RequestPullPredicate<F>
+where
+    F: Fn(&PeerId, &Urn) -> bool + Clone,
+{
+    fn allow(&self, peer: &PeerId, urn: &Urn) -> bool {
+        (self.0)(peer, urn)
+    }
+}
+
 /// Discovery that never provides a boostrap peer
 #[derive(Clone)]
 pub struct NoDiscovery;
@@ -52,3 +188,442 @@ impl discovery::Discovery for NoDiscovery {
         Box::pin(futures::stream::pending())
     }
 }
+
+/// A single DNS bootstrap seed of the form `PeerId@hostname:port`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DnsSeed {
+    /// The peer we expect to find behind `host`.
+    pub peer_id: PeerId,
+    /// Hostname to resolve, e.g. `seed.radicle.xyz`.
+    pub host: String,
+    /// Port the peer's protocol listens on.
+    pub port: u16,
+}
+
+/// Error parsing a [`DnsSeed`] from a `PeerId@hostname:port` string.
+#[derive(Debug, thiserror::Error)]
+pub enum DnsSeedParseError {
+    #[error("seed is missing the `PeerId@` prefix")]
+    MissingPeerId,
+    #[error("seed is missing the `:port` suffix")]
+    MissingPort,
+    #[error("invalid peer id")]
+    PeerId(#[source] Box<dyn std::error::Error + Send + Sync + 'static>),
+    #[error("invalid port")]
+    Port(#[from] std::num::ParseIntError),
+}
+
+impl FromStr for DnsSeed {
+    type Err = DnsSeedParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (peer_id, rest) = s.split_once('@').ok_or(DnsSeedParseError::MissingPeerId)?;
+        let (host, port) = rest
+            .rsplit_once(':')
+            .ok_or(DnsSeedParseError::MissingPort)?;
+        Ok(Self {
+            peer_id: peer_id
+                .parse()
+                .map_err(|err: <PeerId as FromStr>::Err| DnsSeedParseError::PeerId(Box::new(err)))?,
+            host: host.to_string(),
+            port: port.parse()?,
+        })
+    }
+}
+
+/// Discovery that resolves a static list of [`DnsSeed`]s on a fixed `interval`.
+///
+/// Hostnames are re-resolved every `interval` so that DNS changes (e.g. a seed
+/// node moving to a new address) are picked up without restarting the peer.
+/// Multiple `A`/`AAAA` records for a single seed are all surfaced as bootstrap
+/// addresses for that peer. Seeds that fail to resolve are logged and skipped
+/// rather than aborting discovery for the remaining seeds.
+#[derive(Clone)]
+pub struct DnsDiscovery {
+    seeds: Vec<DnsSeed>,
+    interval: Duration,
+}
+
+impl DnsDiscovery {
+    /// Re-resolve `seeds` every `interval`.
+    pub fn new(seeds: Vec<DnsSeed>, interval: Duration) -> Self {
+        Self { seeds, interval }
+    }
+}
+
+impl discovery::Discovery for DnsDiscovery {
+    type Addr = SocketAddr;
+    type Stream = BoxStream<'static, (PeerId, Vec<SocketAddr>)>;
+
+    fn discover(self) -> Self::Stream {
+        let Self { seeds, interval } = self;
+        Box::pin(futures::stream::unfold(
+            (seeds, 0usize),
+            move |(seeds, idx)| async move { dns_discovery_next(seeds, idx, interval).await },
+        ))
+    }
+}
+
+/// mDNS service type Upstream peers advertise themselves under and browse for.
+const MDNS_SERVICE_TYPE: &str = "_radicle-upstream._udp.local.";
+
+/// Discovery that finds other Upstream peers on the local network via mDNS.
+///
+/// It advertises our own `peer_id` and `listen_addr` as a service record,
+/// browses for other Upstream records on the link-local multicast group, and
+/// pushes `(PeerId, Vec<SocketAddr>)` pairs onto its stream as peers are
+/// discovered or re-announce themselves under different addresses. Our own
+/// advertisement and repeated announcements that report the same addresses
+/// as the last one seen for that peer are filtered out.
+#[derive(Clone)]
+pub struct MdnsDiscovery {
+    peer_id: PeerId,
+    listen_addr: SocketAddr,
+}
+
+impl MdnsDiscovery {
+    /// Advertise `peer_id` as reachable at `listen_addr` and browse for peers
+    /// doing the same.
+    pub fn new(peer_id: PeerId, listen_addr: SocketAddr) -> Self {
+        Self {
+            peer_id,
+            listen_addr,
+        }
+    }
+}
+
+impl discovery::Discovery for MdnsDiscovery {
+    type Addr = SocketAddr;
+    type Stream = BoxStream<'static, (PeerId, Vec<SocketAddr>)>;
+
+    fn discover(self) -> Self::Stream {
+        let Self {
+            peer_id,
+            listen_addr,
+        } = self;
+
+        let state = match MdnsState::new(peer_id, listen_addr) {
+            Ok(state) => state,
+            Err(err) => {
+                tracing::warn!(%err, "mDNS unavailable, disabling local-network discovery");
+                return Box::pin(futures::stream::pending());
+            },
+        };
+
+        Box::pin(futures::stream::unfold(state, |mut state| async move {
+            loop {
+                let event = state.receiver.recv_async().await.ok()?;
+                if let Some(discovered) = mdns_handle_event(event, state.our_peer_id, &mut state.seen)
+                {
+                    return Some((discovered, state));
+                }
+            }
+        }))
+    }
+}
+
+/// Live mDNS daemon handle plus the addresses last reported for each peer, so
+/// that an unchanged announcement doesn't get re-pushed onto the stream but a
+/// peer reappearing under new addresses (DHCP renewal, different interface)
+/// does.
+struct MdnsState {
+    our_peer_id: PeerId,
+    daemon: mdns_sd::ServiceDaemon,
+    receiver: flume::Receiver<mdns_sd::ServiceEvent>,
+    seen: std::collections::HashMap<PeerId, Vec<SocketAddr>>,
+}
+
+impl MdnsState {
+    fn new(peer_id: PeerId, listen_addr: SocketAddr) -> Result<Self, mdns_sd::Error> {
+        let daemon = mdns_sd::ServiceDaemon::new()?;
+
+        let instance_name = peer_id.to_string();
+        let service = mdns_sd::ServiceInfo::new(
+            MDNS_SERVICE_TYPE,
+            &instance_name,
+            &format!("{}.local.", instance_name),
+            listen_addr.ip(),
+            listen_addr.port(),
+            None,
+        )?;
+        daemon.register(service)?;
+
+        let receiver = daemon.browse(MDNS_SERVICE_TYPE)?;
+
+        Ok(Self {
+            our_peer_id: peer_id,
+            daemon,
+            receiver,
+            seen: std::collections::HashMap::new(),
+        })
+    }
+}
+
+impl Drop for MdnsState {
+    fn drop(&mut self) {
+        let _ = self.daemon.shutdown();
+    }
+}
+
+/// Turn a raw mDNS event into a freshly discovered peer, if any. Returns
+/// `None` for events that are not resolved peers, are our own advertisement,
+/// or report the same addresses already recorded in `seen` for that peer.
+fn mdns_handle_event(
+    event: mdns_sd::ServiceEvent,
+    our_peer_id: PeerId,
+    seen: &mut std::collections::HashMap<PeerId, Vec<SocketAddr>>,
+) -> Option<(PeerId, Vec<SocketAddr>)> {
+    let info = match event {
+        mdns_sd::ServiceEvent::ServiceResolved(info) => info,
+        _ => return None,
+    };
+
+    let peer_id: PeerId = info.get_fullname().split('.').next()?.parse().ok()?;
+    if peer_id == our_peer_id {
+        return None;
+    }
+
+    let port = info.get_port();
+    let addrs = info
+        .get_addresses()
+        .iter()
+        .map(|ip| SocketAddr::new(*ip, port))
+        .collect::<Vec<_>>();
+    if addrs.is_empty() {
+        return None;
+    }
+
+    if seen.get(&peer_id) == Some(&addrs) {
+        return None;
+    }
+    seen.insert(peer_id, addrs.clone());
+
+    Some((peer_id, addrs))
+}
+
+/// Resolve the next seed in round-robin order, sleeping for `interval` once a
+/// full round has completed, and skipping (without ending the stream) any
+/// seed that fails to resolve.
+async fn dns_discovery_next(
+    seeds: Vec<DnsSeed>,
+    mut idx: usize,
+    interval: Duration,
+) -> Option<((PeerId, Vec<SocketAddr>), (Vec<DnsSeed>, usize))> {
+    if seeds.is_empty() {
+        return None;
+    }
+    loop {
+        if idx > 0 && idx % seeds.len() == 0 {
+            tokio::time::sleep(interval).await;
+        }
+        let seed = &seeds[idx % seeds.len()];
+        idx += 1;
+        match tokio::net::lookup_host((seed.host.as_str(), seed.port)).await {
+            Ok(resolved) => {
+                let addrs = resolved.collect::<Vec<_>>();
+                if addrs.is_empty() {
+                    tracing::warn!(host = %seed.host, port = seed.port, "dns seed resolved to no addresses, skipping");
+                    continue;
+                }
+                let peer_id = seed.peer_id;
+                return Some(((peer_id, addrs), (seeds, idx)));
+            },
+            Err(err) => {
+                tracing::warn!(host = %seed.host, port = seed.port, %err, "failed to resolve dns seed, skipping");
+                continue;
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use net::protocol::config::RequestPullGuard as _;
+
+    fn fresh_peer_id() -> PeerId {
+        PeerId::from(librad::keys::SecretKey::new())
+    }
+
+    fn nil_urn() -> Urn {
+        Urn::new(
+            "0000000000000000000000000000000000000000"
+                .parse::<Oid>()
+                .unwrap(),
+        )
+    }
+
+    #[test]
+    fn dns_seed_parses_valid_entry() {
+        let peer_id = fresh_peer_id();
+        let seed: DnsSeed = format!("{}@seed.radicle.xyz:12345", peer_id).parse().unwrap();
+        assert_eq!(seed.peer_id, peer_id);
+        assert_eq!(seed.host, "seed.radicle.xyz");
+        assert_eq!(seed.port, 12345);
+    }
+
+    #[test]
+    fn dns_seed_rejects_missing_peer_id() {
+        let err = "seed.radicle.xyz:12345".parse::<DnsSeed>().unwrap_err();
+        assert!(matches!(err, DnsSeedParseError::MissingPeerId));
+    }
+
+    #[test]
+    fn dns_seed_rejects_missing_port() {
+        let peer_id = fresh_peer_id();
+        let err = format!("{}@seed.radicle.xyz", peer_id)
+            .parse::<DnsSeed>()
+            .unwrap_err();
+        assert!(matches!(err, DnsSeedParseError::MissingPort));
+    }
+
+    #[test]
+    fn dns_seed_rejects_invalid_peer_id() {
+        let err = "not-a-peer-id@seed.radicle.xyz:12345"
+            .parse::<DnsSeed>()
+            .unwrap_err();
+        assert!(matches!(err, DnsSeedParseError::PeerId(_)));
+    }
+
+    #[test]
+    fn dns_seed_rejects_invalid_port() {
+        let peer_id = fresh_peer_id();
+        let err = format!("{}@seed.radicle.xyz:notaport", peer_id)
+            .parse::<DnsSeed>()
+            .unwrap_err();
+        assert!(matches!(err, DnsSeedParseError::Port(_)));
+    }
+
+    #[tokio::test]
+    async fn dns_discovery_next_returns_none_for_no_seeds() {
+        assert!(dns_discovery_next(vec![], 0, Duration::from_millis(1))
+            .await
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn dns_discovery_next_round_robins_and_wraps() {
+        let seeds = vec![
+            DnsSeed {
+                peer_id: fresh_peer_id(),
+                host: "127.0.0.1".to_string(),
+                port: 1,
+            },
+            DnsSeed {
+                peer_id: fresh_peer_id(),
+                host: "127.0.0.1".to_string(),
+                port: 2,
+            },
+        ];
+
+        let ((first_peer, _), (seeds, idx)) =
+            dns_discovery_next(seeds, 0, Duration::from_millis(1))
+                .await
+                .unwrap();
+        assert_eq!(first_peer, seeds[0].peer_id);
+        assert_eq!(idx, 1);
+
+        let ((second_peer, _), (seeds, idx)) =
+            dns_discovery_next(seeds, idx, Duration::from_millis(1))
+                .await
+                .unwrap();
+        assert_eq!(second_peer, seeds[1].peer_id);
+        assert_eq!(idx, 2);
+
+        // A full round has completed (idx == seeds.len()), so this call
+        // sleeps briefly before wrapping back to the first seed.
+        let ((third_peer, _), (_, idx)) = dns_discovery_next(seeds, idx, Duration::from_millis(1))
+            .await
+            .unwrap();
+        assert_eq!(third_peer, first_peer);
+        assert_eq!(idx, 3);
+    }
+
+    fn resolved_event(peer_id: PeerId, addr: SocketAddr) -> mdns_sd::ServiceEvent {
+        let instance_name = peer_id.to_string();
+        let info = mdns_sd::ServiceInfo::new(
+            MDNS_SERVICE_TYPE,
+            &instance_name,
+            &format!("{}.local.", instance_name),
+            addr.ip(),
+            addr.port(),
+            None,
+        )
+        .unwrap();
+        mdns_sd::ServiceEvent::ServiceResolved(info)
+    }
+
+    #[test]
+    fn mdns_handle_event_filters_out_self() {
+        let our_peer_id = fresh_peer_id();
+        let mut seen = std::collections::HashMap::new();
+        let event = resolved_event(our_peer_id, "127.0.0.1:1".parse().unwrap());
+
+        assert!(mdns_handle_event(event, our_peer_id, &mut seen).is_none());
+    }
+
+    #[test]
+    fn mdns_handle_event_reports_new_peer_once_then_suppresses_repeat() {
+        let our_peer_id = fresh_peer_id();
+        let their_peer_id = fresh_peer_id();
+        let addr: SocketAddr = "127.0.0.1:1".parse().unwrap();
+        let mut seen = std::collections::HashMap::new();
+
+        let discovered =
+            mdns_handle_event(resolved_event(their_peer_id, addr), our_peer_id, &mut seen);
+        assert_eq!(discovered, Some((their_peer_id, vec![addr])));
+
+        // Same address announced again: already recorded, so no repeat event.
+        let repeat =
+            mdns_handle_event(resolved_event(their_peer_id, addr), our_peer_id, &mut seen);
+        assert!(repeat.is_none());
+    }
+
+    #[test]
+    fn mdns_handle_event_resurfaces_peer_whose_address_changed() {
+        let our_peer_id = fresh_peer_id();
+        let their_peer_id = fresh_peer_id();
+        let old_addr: SocketAddr = "127.0.0.1:1".parse().unwrap();
+        let new_addr: SocketAddr = "127.0.0.1:2".parse().unwrap();
+        let mut seen = std::collections::HashMap::new();
+
+        mdns_handle_event(resolved_event(their_peer_id, old_addr), our_peer_id, &mut seen);
+
+        // Same peer, new address (e.g. DHCP lease renewal): should be
+        // reported again rather than permanently suppressed.
+        let discovered = mdns_handle_event(
+            resolved_event(their_peer_id, new_addr),
+            our_peer_id,
+            &mut seen,
+        );
+        assert_eq!(discovered, Some((their_peer_id, vec![new_addr])));
+    }
+
+    #[test]
+    fn allowed_peers_denies_everyone_when_empty() {
+        let guard = AllowedPeers::default();
+        assert!(!guard.allow(&fresh_peer_id(), &nil_urn()));
+    }
+
+    #[test]
+    fn allowed_peers_allows_listed_peer() {
+        let peer_id = fresh_peer_id();
+        let guard = AllowedPeers::new([peer_id]);
+        assert!(guard.allow(&peer_id, &nil_urn()));
+    }
+
+    #[test]
+    fn allowed_peers_denies_unlisted_peer() {
+        let guard = AllowedPeers::new([fresh_peer_id()]);
+        assert!(!guard.allow(&fresh_peer_id(), &nil_urn()));
+    }
+
+    #[test]
+    fn request_pull_predicate_delegates_to_closure() {
+        let allowed_peer = fresh_peer_id();
+        let guard = RequestPullPredicate::new(move |peer: &PeerId, _urn: &Urn| *peer == allowed_peer);
+
+        assert!(guard.allow(&allowed_peer, &nil_urn()));
+        assert!(!guard.allow(&fresh_peer_id(), &nil_urn()));
+    }
+}